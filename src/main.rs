@@ -1,50 +1,66 @@
+use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use chrono::DateTime;
+use atom_syndication::Entry;
+use atom_syndication::Feed;
+use atom_syndication::Link;
+use atom_syndication::Person;
+use chrono::Datelike;
 use chrono::Utc;
 use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
 use clap::ValueHint;
 use eyre::Context;
 use eyre::Result;
 use eyre::bail;
+use eyre::eyre;
+use graphql_client::GraphQLQuery;
+use reqwest::header::ACCEPT;
 use reqwest::header::AUTHORIZATION;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
+use reqwest::header::RETRY_AFTER;
 use reqwest::header::USER_AGENT;
 use serde::Deserialize;
 use serde::Serialize;
 use tokio::time::sleep;
 
-/// A query to send off to the GitHub GraphQL API.
-#[derive(Serialize)]
-struct Query<'a> {
-    /// The query.
-    query: &'a str,
-}
+/// The `DateTime` custom scalar used by the GitHub schema, mapped onto
+/// `chrono`'s UTC date-time so the generated query deserializes directly.
+type DateTime = chrono::DateTime<Utc>;
+
+/// The typed stargazers query, generated from `query/stargazers.graphql` and
+/// checked against `schema.graphql` at compile time.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.graphql",
+    query_path = "query/stargazers.graphql",
+    response_derives = "Debug"
+)]
+struct StargazersQuery;
 
 /// A star event for a repository.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct Star {
     /// The time the repository was starred.
-    #[serde(rename = "starredAt")]
-    starred_at: DateTime<Utc>,
+    starred_at: DateTime,
 
     /// The user that starred the repo.
     node: User,
 }
 
 /// A followers/following count.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct FollowCount {
     /// The total number of followers/following.
-    #[serde(rename = "totalCount")]
     total_count: usize,
 }
 
 /// A Github user.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct User {
     /// The user's username.
     login: String,
@@ -62,59 +78,88 @@ struct User {
     following: FollowCount,
 
     /// If the user marked themselves as hireable.
-    #[serde(rename = "isHireable")]
     is_hireable: bool,
-}
 
-/// The entire GraphQL response.
-#[derive(Debug, Deserialize)]
-struct Response {
-    /// The response data.
-    data: ResponseData,
+    /// Richer profile data, populated only in `--enrich` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enrichment: Option<Enrichment>,
 }
 
-/// The response data.
-#[derive(Debug, Deserialize)]
-struct ResponseData {
-    /// The repository.
-    repository: Repository,
+/// A summary of a single repository, used for a user's most-starred and
+/// pinned repositories.
+#[derive(Debug, Serialize, Deserialize)]
+struct RepoSummary {
+    /// The repository name.
+    name: String,
+
+    /// The number of stars the repository has.
+    #[serde(rename = "stargazerCount")]
+    stargazer_count: usize,
+
+    /// The number of forks the repository has.
+    #[serde(rename = "forkCount")]
+    fork_count: usize,
 }
 
-/// The repository in the GraphQL response.
-#[derive(Debug, Deserialize)]
-struct Repository {
-    /// The stargazers for that repository.
-    stargazers: Stargazers,
+/// Richer profile data fetched per stargazer in `--enrich` mode.
+#[derive(Debug, Serialize, Deserialize)]
+struct Enrichment {
+    /// The user's bio.
+    bio: Option<String>,
+
+    /// The company the user belongs to.
+    company: Option<String>,
+
+    /// The user's Twitter handle.
+    twitter_username: Option<String>,
+
+    /// When the user's account was created.
+    created_at: DateTime,
+
+    /// The number of public repositories the user owns.
+    public_repos: usize,
+
+    /// The user's most-starred repository, if any.
+    top_repository: Option<RepoSummary>,
+
+    /// The repositories the user has pinned to their profile.
+    pinned_repositories: Vec<RepoSummary>,
 }
 
-/// The stargazers for a GitHub repository.
-#[derive(Debug, Deserialize)]
-struct Stargazers {
-    /// The edges in the stars graph.
-    edges: Vec<Star>,
+/// The current state of the GraphQL rate limit, as reported by the API.
+#[derive(Debug)]
+struct RateLimit {
+    /// The point cost of the issuing query.
+    cost: i64,
 
-    /// The pagination information.
-    #[serde(rename = "pageInfo")]
-    page_info: PageInfo,
+    /// The number of points remaining in the current window.
+    remaining: i64,
+
+    /// The time at which the current window resets.
+    reset_at: DateTime,
 }
 
-/// The pagination information.
-#[derive(Debug, Deserialize)]
-struct PageInfo {
+/// A single fetched page of stargazers, decoded into the domain model.
+#[derive(Debug)]
+struct Page {
+    /// The stars on this page.
+    stars: Vec<Star>,
+
     /// Whether or not a next page exists.
-    #[serde(rename = "hasNextPage")]
     has_next_page: bool,
 
-    /// The last cursor.
-    #[serde(rename = "endCursor")]
+    /// The cursor to pass as `after` to fetch the following page.
     end_cursor: Option<String>,
+
+    /// The rate-limit state reported alongside this page, if any.
+    rate_limit: Option<RateLimit>,
 }
 
 /// A row in the final CSV table.
 #[derive(Debug, Serialize)]
 struct Row {
     /// The date the star was given.
-    date: DateTime<Utc>,
+    date: DateTime,
 
     /// The username of the individual giving the star.
     username: String,
@@ -133,150 +178,1293 @@ struct Row {
 
     /// Whether or not the individual is hireable.
     hireable: bool,
+
+    /// The individual's bio.
+    bio: Option<String>,
+
+    /// The company the individual belongs to.
+    company: Option<String>,
+
+    /// The individual's Twitter handle.
+    twitter: Option<String>,
+
+    /// When the individual's account was created.
+    created_at: Option<DateTime>,
+
+    /// The number of public repositories the individual owns.
+    public_repos: Option<usize>,
+
+    /// The individual's most-starred repository, as `name (stars/forks)`.
+    top_repository: Option<String>,
+
+    /// The individual's pinned repositories, joined as `name (stars/forks)`.
+    pinned_repositories: Option<String>,
 }
 
-/// Writes a list of stargazers to a CSV.
+/// Formats a repository summary as `name (stars/forks)` for the CSV.
+fn format_repo(summary: &RepoSummary) -> String {
+    format!(
+        "{} ({}/{})",
+        summary.name, summary.stargazer_count, summary.fork_count
+    )
+}
+
+/// A point in the cumulative star-count time series.
+#[derive(Debug, Serialize)]
+struct HistoryRow {
+    /// The (possibly bucketed) timestamp.
+    timestamp: DateTime,
+
+    /// The running total of stars up to and including this point. For a
+    /// bucketed timestamp this is the total at the end of its period.
+    cumulative_count: usize,
+}
+
+/// The period boundary that timestamps are bucketed to in `history` mode.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Granularity {
+    /// Emit one point per star, at its exact timestamp.
+    Raw,
+
+    /// Bucket to the start of the day (UTC).
+    Daily,
+
+    /// Bucket to the Monday of the week (UTC).
+    Weekly,
+
+    /// Bucket to the first of the month (UTC).
+    Monthly,
+}
+
+/// The output format for `fetch` mode.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FetchFormat {
+    /// A CSV table with one row per stargazer.
+    Csv,
+
+    /// An Atom syndication feed with one entry per stargazer.
+    Atom,
+}
+
+/// The output format for `history` mode.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum HistoryFormat {
+    /// A two-column `timestamp,cumulative_count` CSV.
+    Csv,
+
+    /// A simple SVG line chart of star growth over time.
+    Svg,
+}
+
+/// The common arguments identifying the repository to crawl.
 #[derive(Debug, Parser)]
-pub struct Args {
+struct RepoArgs {
     /// The organization or owner of the repository.
     owner: String,
 
     /// The repository.
     repository: String,
+}
+
+/// The subcommand to run.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Writes a list of stargazers to a CSV or an Atom feed.
+    Fetch(FetchArgs),
+
+    /// Produces a cumulative star-count time series for plotting star growth.
+    History(HistoryArgs),
+
+    /// Authenticates with GitHub via the OAuth device flow and caches a token.
+    Auth,
+}
+
+/// The top-level command line.
+#[derive(Debug, Parser)]
+struct Args {
+    /// The subcommand to run.
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Writes a list of stargazers to a CSV or an Atom feed.
+#[derive(Debug, Parser)]
+struct FetchArgs {
+    /// The repository to crawl.
+    #[command(flatten)]
+    repo: RepoArgs,
 
     /// The path to the output file.
     #[arg(short, long, value_hint = ValueHint::FilePath)]
     path: Option<PathBuf>,
+
+    /// Ignore any cached pages and re-fetch the whole crawl from scratch.
+    #[arg(long)]
+    refresh: bool,
+
+    /// Fetch richer profile data for each stargazer (an extra query per batch).
+    #[arg(long)]
+    enrich: bool,
+
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = FetchFormat::Csv)]
+    format: FetchFormat,
+
+    /// Cap the output at the N most recent stars (Atom feeds only).
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+/// Produces a cumulative star-count time series.
+#[derive(Debug, Parser)]
+struct HistoryArgs {
+    /// The repository to crawl.
+    #[command(flatten)]
+    repo: RepoArgs,
+
+    /// The path to the output file.
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    path: Option<PathBuf>,
+
+    /// Ignore any cached pages and re-fetch the whole crawl from scratch.
+    #[arg(long)]
+    refresh: bool,
+
+    /// The period that timestamps are bucketed to.
+    #[arg(long, value_enum, default_value_t = Granularity::Raw)]
+    granularity: Granularity,
+
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = HistoryFormat::Csv)]
+    format: HistoryFormat,
 }
 
 /// Fetches a single page of results for the stargazers.
 async fn fetch_page(
-    args: &Args,
+    repo: &RepoArgs,
     token: &str,
-    count: usize,
+    count: i64,
     after: Option<String>,
-) -> Result<Response> {
-    let after_clause = match after {
-        Some(n) => format!(r#", after: "{}""#, n),
-        None => String::new(),
+) -> Result<Page> {
+    let variables = stargazers_query::Variables {
+        owner: repo.owner.clone(),
+        name: repo.repository.clone(),
+        first: count,
+        after,
+    };
+    let body = StargazersQuery::build_query(variables);
+
+    let client = reqwest::Client::new();
+    let mut headers = HeaderMap::new();
+
+    headers.insert(USER_AGENT, HeaderValue::from_str("star-tracker/v0")?);
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", token))?,
+    );
+
+    // Secondary rate limits surface as 403/429 responses; honor `Retry-After`
+    // when present and otherwise back off exponentially before retrying.
+    const MAX_RETRIES: u32 = 5;
+    let mut attempt = 0;
+
+    let response = loop {
+        let response = client
+            .post("https://api.github.com/graphql")
+            .headers(headers.clone())
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            break response;
+        }
+
+        if matches!(status.as_u16(), 403 | 429) && attempt < MAX_RETRIES {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok());
+            let backoff = 2u64.pow(attempt);
+            let delay = retry_after.unwrap_or(backoff);
+
+            eprintln!("throttled ({status}); retrying in {delay}s");
+            attempt += 1;
+            sleep(Duration::from_secs(delay)).await;
+            continue;
+        }
+
+        bail!(
+            "failed to fetch data from GitHub GraphQL API: {:?}",
+            status
+        )
     };
 
+    let response: graphql_client::Response<stargazers_query::ResponseData> = response
+        .json()
+        .await
+        .context("serializing GitHub response to JSON")?;
+
+    if let Some(errors) = response.errors {
+        if !errors.is_empty() {
+            bail!("GitHub GraphQL API returned errors: {:?}", errors);
+        }
+    }
+
+    let data = response
+        .data
+        .ok_or_else(|| eyre!("GitHub GraphQL API returned no data"))?;
+    let rate_limit = data.rate_limit.map(|rate_limit| RateLimit {
+        cost: rate_limit.cost,
+        remaining: rate_limit.remaining,
+        reset_at: rate_limit.reset_at,
+    });
+    let repository = data
+        .repository
+        .ok_or_else(|| eyre!("repository {}/{} not found", repo.owner, repo.repository))?;
+    let stargazers = repository.stargazers;
+
+    let stars = stargazers
+        .edges
+        .into_iter()
+        .map(|edge| Star {
+            starred_at: edge.starred_at,
+            node: User {
+                login: edge.node.login,
+                email: edge.node.email,
+                location: edge.node.location,
+                followers: FollowCount {
+                    total_count: edge.node.followers.total_count as usize,
+                },
+                following: FollowCount {
+                    total_count: edge.node.following.total_count as usize,
+                },
+                is_hireable: edge.node.is_hireable,
+                enrichment: None,
+            },
+        })
+        .collect();
+
+    Ok(Page {
+        stars,
+        has_next_page: stargazers.page_info.has_next_page,
+        end_cursor: stargazers.page_info.end_cursor,
+        rate_limit,
+    })
+}
+
+/// A page as persisted to the on-disk cache. The rate-limit state is
+/// intentionally omitted — it is transient and only relevant while crawling.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPage {
+    /// The stars on this page.
+    stars: Vec<Star>,
+
+    /// Whether or not a next page exists.
+    has_next_page: bool,
+
+    /// The cursor to pass as `after` to fetch the following page.
+    end_cursor: Option<String>,
+}
+
+/// An on-disk store for successfully fetched pages, keyed by `owner/repo` and
+/// the cursor each page was fetched with. Persisting pages as they arrive lets
+/// an interrupted crawl resume from where it left off instead of starting
+/// over, and spares the API on repeated runs. Resume is driven entirely by the
+/// page cache: `fetch` replays the cursor chain from the start, serving each
+/// page from disk until it reaches the first one that was never fetched.
+struct TempCache {
+    /// The per-repository directory holding the cached pages.
+    dir: PathBuf,
+}
+
+impl TempCache {
+    /// Opens (creating if necessary) the cache directory for a repository.
+    fn open(repo: &RepoArgs) -> Result<Self> {
+        let dir = env::temp_dir()
+            .join("track-stars")
+            .join(format!("{}-{}", repo.owner, repo.repository));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("creating cache directory at {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// The path of the cached page fetched with the given `after` cursor.
+    fn page_path(&self, after: &Option<String>) -> PathBuf {
+        use std::hash::Hash;
+        use std::hash::Hasher;
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        after.hash(&mut hasher);
+        self.dir.join(format!("page-{:016x}.json", hasher.finish()))
+    }
+
+    /// Reads the page fetched with `after` from the cache, if present.
+    fn get(&self, after: &Option<String>) -> Result<Option<CachedPage>> {
+        let path = self.page_path(after);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents =
+            std::fs::read(&path).with_context(|| format!("reading cache at {}", path.display()))?;
+        let page = serde_json::from_slice(&contents)
+            .with_context(|| format!("deserializing cache at {}", path.display()))?;
+        Ok(Some(page))
+    }
+
+    /// Persists a freshly fetched page to the cache.
+    fn put(&self, after: &Option<String>, page: &CachedPage) -> Result<()> {
+        let path = self.page_path(after);
+        let contents = serde_json::to_vec(page).context("serializing page for cache")?;
+        std::fs::write(&path, contents)
+            .with_context(|| format!("writing cache at {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Removes all cached pages, forcing the next crawl to re-fetch. The cache
+    /// holds only page files; no separate cursor state is stored.
+    fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)
+                .with_context(|| format!("clearing cache at {}", self.dir.display()))?;
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating cache directory at {}", self.dir.display()))?;
+        Ok(())
+    }
+}
+
+/// The number of stargazers enriched per request. GitHub resolves users by
+/// login individually, so a batch is built from that many aliased `user`
+/// nodes rather than a single list field.
+const ENRICH_BATCH: usize = 50;
+
+/// The shared selection set for an enriched user profile.
+const USER_PROFILE_FRAGMENT: &str = r#"
+fragment UserProfile on User {
+    login
+    bio
+    company
+    twitterUsername
+    createdAt
+    repositories(privacy: PUBLIC) {
+        totalCount
+    }
+    topRepository: repositories(first: 1, orderBy: {field: STARGAZERS, direction: DESC}) {
+        nodes {
+            name
+            stargazerCount
+            forkCount
+        }
+    }
+    pinnedItems(first: 6, types: [REPOSITORY]) {
+        nodes {
+            ... on Repository {
+                name
+                stargazerCount
+                forkCount
+            }
+        }
+    }
+}
+"#;
+
+/// A request body for the dynamically-aliased enrichment query. Unlike the
+/// stargazers query this cannot be derived with `graphql_client`, because the
+/// number of aliased `user` nodes varies per batch — but every login is still
+/// passed as a typed variable rather than interpolated into the query body.
+#[derive(Serialize)]
+struct EnrichBody {
+    /// The generated query text.
+    query: String,
+
+    /// The `$lN` login variables referenced by the query.
+    variables: HashMap<String, String>,
+}
+
+/// An enriched user node as returned by the GraphQL API.
+#[derive(Debug, Deserialize)]
+struct EnrichedUser {
+    /// The user's username.
+    login: String,
+
+    /// The user's bio.
+    bio: Option<String>,
+
+    /// The company the user belongs to.
+    company: Option<String>,
+
+    /// The user's Twitter handle.
+    #[serde(rename = "twitterUsername")]
+    twitter_username: Option<String>,
+
+    /// When the user's account was created.
+    #[serde(rename = "createdAt")]
+    created_at: DateTime,
+
+    /// The user's public repository count.
+    repositories: RepoCount,
+
+    /// The user's most-starred repository.
+    #[serde(rename = "topRepository")]
+    top_repository: RepoNodes,
+
+    /// The user's pinned repositories.
+    #[serde(rename = "pinnedItems")]
+    pinned_items: RepoNodes,
+}
+
+/// A repository `totalCount`, as returned on the wire by the enrichment query.
+#[derive(Debug, Deserialize)]
+struct RepoCount {
+    /// The total number of repositories.
+    #[serde(rename = "totalCount")]
+    total_count: usize,
+}
+
+/// A list of repository summaries under a `nodes` key.
+#[derive(Debug, Deserialize)]
+struct RepoNodes {
+    /// The repository summaries.
+    nodes: Vec<RepoSummary>,
+}
+
+/// The enrichment query response. Unlike `fetch_page`, an aliased batch can
+/// come back with partial `data` alongside per-alias errors, so this is
+/// deserialized directly to inspect the error `type`.
+#[derive(Debug, Deserialize)]
+struct EnrichResponse {
+    /// The resolved user nodes, keyed by alias. Unresolvable logins are `null`.
+    data: Option<HashMap<String, Option<EnrichedUser>>>,
+
+    /// Any GraphQL errors reported alongside the data.
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+/// A single GraphQL error entry.
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    /// The human-readable error message.
+    message: String,
+
+    /// The error type, e.g. `NOT_FOUND` for an unresolvable login.
+    #[serde(rename = "type")]
+    error_type: Option<String>,
+}
+
+impl EnrichResponse {
+    /// Extracts the user nodes, tolerating per-alias `NOT_FOUND` errors (the
+    /// corresponding node is already `null` and simply skipped). Bails when no
+    /// data was returned or any other error occurred.
+    fn into_users(self) -> Result<HashMap<String, Option<EnrichedUser>>> {
+        let fatal = self
+            .errors
+            .iter()
+            .filter(|error| error.error_type.as_deref() != Some("NOT_FOUND"))
+            .collect::<Vec<_>>();
+
+        match self.data {
+            Some(users) if fatal.is_empty() => Ok(users),
+            Some(_) => bail!("GitHub GraphQL API returned errors: {:?}", fatal),
+            None => bail!("GitHub GraphQL API returned no data: {:?}", self.errors),
+        }
+    }
+}
+
+/// Builds the enrichment query text and login variables for a batch.
+fn build_enrich_body(logins: &[String]) -> EnrichBody {
+    let mut definitions = Vec::with_capacity(logins.len());
+    let mut selections = String::new();
+    let mut variables = HashMap::with_capacity(logins.len());
+
+    for (index, login) in logins.iter().enumerate() {
+        definitions.push(format!("$l{index}: String!"));
+        selections.push_str(&format!(
+            "    u{index}: user(login: $l{index}) {{ ...UserProfile }}\n"
+        ));
+        variables.insert(format!("l{index}"), login.clone());
+    }
+
     let query = format!(
-        r#"
-        {{
-            repository(owner: "{}", name: "{}") {{
-                stargazers(first: {}{}) {{
-                    edges {{
-                        starredAt,
-                        node {{
-                          name,
-                          email,
-                          login,
-                          location,
-                          followers {{
-                            totalCount
-                          }},
-                          following {{
-                            totalCount
-                          }},
-                          isHireable
-                        }}
-                    }}
-                    pageInfo {{
-                        hasNextPage
-                        endCursor
-                    }}
-                }}
-            }}
-        }}
-        "#,
-        args.owner, args.repository, count, after_clause
+        "query Enrich({}) {{\n{}}}\n{}",
+        definitions.join(", "),
+        selections,
+        USER_PROFILE_FRAGMENT
     );
 
+    EnrichBody { query, variables }
+}
+
+/// Fetches richer profile data for the given logins, batching them into
+/// aliased queries. Logins that the API cannot resolve are simply absent from
+/// the returned map.
+async fn enrich(logins: &[String], token: &str) -> Result<HashMap<String, Enrichment>> {
     let client = reqwest::Client::new();
     let mut headers = HeaderMap::new();
-
     headers.insert(USER_AGENT, HeaderValue::from_str("star-tracker/v0")?);
     headers.insert(
         AUTHORIZATION,
         HeaderValue::from_str(&format!("Bearer {}", token))?,
     );
 
-    sleep(Duration::from_secs(3)).await;
+    let mut enrichments = HashMap::new();
 
-    let request = client
-        .post("https://api.github.com/graphql")
-        .headers(headers)
-        .json(&Query { query: &query });
+    for (batch, chunk) in logins.chunks(ENRICH_BATCH).enumerate() {
+        eprintln!("Enriching batch {} ({} users)", batch + 1, chunk.len());
 
-    let response = request.send().await?;
+        let body = build_enrich_body(chunk);
+        let response = client
+            .post("https://api.github.com/graphql")
+            .headers(headers.clone())
+            .json(&body)
+            .send()
+            .await?;
 
-    if response.status().is_success() {
-        response
+        if !response.status().is_success() {
+            bail!(
+                "failed to enrich stargazers from GitHub GraphQL API: {:?}",
+                response.status()
+            )
+        }
+
+        let response: EnrichResponse = response
             .json()
             .await
-            .context("serializing GitHub response to JSON")
-    } else {
-        bail!(
-            "failed to fetch data from GitHub GraphQL API: {:?}",
-            response.status()
-        )
+            .context("deserializing enrichment response")?;
+
+        let users = response.into_users()?;
+
+        for user in users.into_values().flatten() {
+            enrichments.insert(
+                user.login,
+                Enrichment {
+                    bio: user.bio,
+                    company: user.company,
+                    twitter_username: user.twitter_username,
+                    created_at: user.created_at,
+                    public_repos: user.repositories.total_count,
+                    top_repository: user.top_repository.nodes.into_iter().next(),
+                    pinned_repositories: user.pinned_items.nodes,
+                },
+            );
+        }
     }
+
+    Ok(enrichments)
 }
 
-async fn fetch(args: &Args, token: &str) -> Result<Vec<Star>> {
+/// Determines how long to wait before issuing the next request, given the
+/// most recent rate-limit state. While `remaining` is healthy the next page is
+/// issued immediately; as it approaches zero the remaining calls are spread
+/// evenly across the time left until `reset_at`.
+fn pace(rate_limit: &RateLimit) -> Duration {
+    /// The number of remaining points above which we don't throttle at all.
+    const HEALTHY: i64 = 100;
+
+    if rate_limit.remaining > HEALTHY {
+        return Duration::from_secs(0);
+    }
+
+    let seconds_until_reset = (rate_limit.reset_at - Utc::now()).num_seconds().max(0) as u64;
+    Duration::from_secs(seconds_until_reset / rate_limit.remaining.max(1) as u64)
+}
+
+async fn fetch(repo: &RepoArgs, token: &str, refresh: bool) -> Result<Vec<Star>> {
+    let cache = TempCache::open(repo).context("opening page cache")?;
+    if refresh {
+        cache.clear().context("refreshing page cache")?;
+    }
+
     let mut results = Vec::new();
     let mut cursor = None;
     let mut has_next_page = true;
     let mut users = 0;
 
     while has_next_page {
-        let response = fetch_page(args, token, 100, cursor)
+        // Replay from the cache when possible so interrupted crawls resume
+        // without re-hitting the network; only uncached pages are fetched.
+        if let Some(cached) = cache.get(&cursor).context("reading page cache")? {
+            users += cached.stars.len();
+            eprintln!("Users: {users} (cached)");
+
+            results.extend(cached.stars);
+            cursor = cached.end_cursor;
+            has_next_page = cached.has_next_page;
+            continue;
+        }
+
+        let page = fetch_page(repo, token, 100, cursor.clone())
             .await
             .context("querying GitHub")?;
 
-        users += response.data.repository.stargazers.edges.len();
-        eprintln!("Users: {users}");
+        users += page.stars.len();
+        match &page.rate_limit {
+            Some(rate_limit) => eprintln!(
+                "Users: {users} (cost: {}, remaining: {})",
+                rate_limit.cost, rate_limit.remaining
+            ),
+            None => eprintln!("Users: {users}"),
+        }
+
+        let cached = CachedPage {
+            stars: page.stars,
+            has_next_page: page.has_next_page,
+            end_cursor: page.end_cursor,
+        };
+        cache
+            .put(&cursor, &cached)
+            .context("writing page to cache")?;
+
+        results.extend(cached.stars);
+        cursor = cached.end_cursor;
+        has_next_page = cached.has_next_page;
 
-        results.extend(response.data.repository.stargazers.edges);
-        cursor = response.data.repository.stargazers.page_info.end_cursor;
-        has_next_page = response.data.repository.stargazers.page_info.has_next_page;
+        if has_next_page {
+            if let Some(rate_limit) = &page.rate_limit {
+                sleep(pace(rate_limit)).await;
+            }
+        }
     }
 
     Ok(results)
 }
 
+/// Buckets a timestamp to the start of its `granularity` period (UTC).
+fn bucket(timestamp: DateTime, granularity: Granularity) -> DateTime {
+    let date = timestamp.date_naive();
+
+    let truncated = match granularity {
+        Granularity::Raw => return timestamp,
+        Granularity::Daily => date,
+        Granularity::Weekly => {
+            date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+        }
+        Granularity::Monthly => date.with_day(1).expect("the first of the month to be valid"),
+    };
+
+    truncated
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight to be a valid time")
+        .and_utc()
+}
+
+/// Turns a list of stars into a cumulative time series, bucketed to
+/// `granularity`. Stars are sorted ascending by `starred_at`, a running total
+/// is accumulated as the list is walked, and — for bucketed granularities —
+/// the last cumulative value in each period is emitted.
+fn time_series(mut stars: Vec<Star>, granularity: Granularity) -> Vec<HistoryRow> {
+    stars.sort_by_key(|star| star.starred_at);
+
+    let mut series = Vec::new();
+    let mut total = 0;
+    let mut pending: Option<HistoryRow> = None;
+
+    for star in &stars {
+        total += 1;
+
+        match granularity {
+            Granularity::Raw => series.push(HistoryRow {
+                timestamp: star.starred_at,
+                cumulative_count: total,
+            }),
+            _ => {
+                let boundary = bucket(star.starred_at, granularity);
+                match pending {
+                    Some(ref mut row) if row.timestamp == boundary => {
+                        row.cumulative_count = total;
+                    }
+                    Some(row) => {
+                        series.push(row);
+                        pending = Some(HistoryRow {
+                            timestamp: boundary,
+                            cumulative_count: total,
+                        });
+                    }
+                    None => {
+                        pending = Some(HistoryRow {
+                            timestamp: boundary,
+                            cumulative_count: total,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(row) = pending {
+        series.push(row);
+    }
+
+    series
+}
+
+/// Renders a cumulative time series as a simple SVG line chart, mapping the
+/// min/max timestamp onto the horizontal axis and `0..=max_count` onto the
+/// vertical axis of a fixed viewport.
+fn render_svg(series: &[HistoryRow]) -> String {
+    const WIDTH: f64 = 800.0;
+    const HEIGHT: f64 = 400.0;
+    const MARGIN: f64 = 40.0;
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">"#
+    );
+
+    if let (Some(first), Some(last)) = (series.first(), series.last()) {
+        let min_x = first.timestamp.timestamp() as f64;
+        let max_x = last.timestamp.timestamp() as f64;
+        let max_y = last.cumulative_count.max(1) as f64;
+
+        let span_x = (max_x - min_x).max(1.0);
+        let plot_w = WIDTH - 2.0 * MARGIN;
+        let plot_h = HEIGHT - 2.0 * MARGIN;
+
+        let points = series
+            .iter()
+            .map(|row| {
+                let x = MARGIN + (row.timestamp.timestamp() as f64 - min_x) / span_x * plot_w;
+                let y = HEIGHT - MARGIN - row.cumulative_count as f64 / max_y * plot_h;
+                format!("{x:.2},{y:.2}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        svg.push_str(&format!(
+            r#"<polyline fill="none" stroke="#2da44e" stroke-width="2" points="{points}"/>"#
+        ));
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Renders the stars as an Atom syndication feed, newest-first, with one entry
+/// per star linking to the user's profile. Any `--limit` cap is applied by the
+/// caller before enrichment, so every star passed here becomes an entry.
+fn render_atom(repo: &RepoArgs, mut stars: Vec<Star>) -> Result<String> {
+    stars.sort_by(|a, b| b.starred_at.cmp(&a.starred_at));
+
+    let updated = stars
+        .first()
+        .map(|star| star.starred_at)
+        .unwrap_or_else(Utc::now);
+
+    let entries = stars
+        .iter()
+        .map(|star| {
+            let profile = format!("https://github.com/{}", star.node.login);
+
+            let mut author = Person::default();
+            author.set_name(star.node.login.clone());
+
+            let mut link = Link::default();
+            link.set_href(profile.clone());
+
+            let mut entry = Entry::default();
+            entry.set_id(profile);
+            entry.set_title(star.node.login.clone());
+            entry.set_published(Some(star.starred_at.fixed_offset()));
+            entry.set_updated(star.starred_at.fixed_offset());
+            entry.set_authors(vec![author]);
+            entry.set_links(vec![link]);
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    let mut feed = Feed::default();
+    feed.set_title(format!("{}/{} stargazers", repo.owner, repo.repository));
+    feed.set_id(format!(
+        "https://github.com/{}/{}/stargazers",
+        repo.owner, repo.repository
+    ));
+    feed.set_updated(updated.fixed_offset());
+    feed.set_entries(entries);
+
+    Ok(feed.to_string())
+}
+
+/// Crawls a repository's stargazers and writes them to a CSV or an Atom feed.
+async fn run_fetch(args: FetchArgs, token: &str) -> Result<()> {
+    if args.limit.is_some() && matches!(args.format, FetchFormat::Csv) {
+        bail!("--limit is only supported with --format atom");
+    }
+
+    let mut stars = fetch(&args.repo, token, args.refresh)
+        .await
+        .context("fetching stargazers")?;
+
+    // Apply the cap before enrichment so `--enrich --limit N` only enriches the
+    // N stars that will actually be emitted, not the entire crawl.
+    if let Some(limit) = args.limit {
+        stars.sort_by(|a, b| b.starred_at.cmp(&a.starred_at));
+        stars.truncate(limit);
+    }
+
+    if args.enrich {
+        let logins = stars
+            .iter()
+            .map(|star| star.node.login.clone())
+            .collect::<Vec<_>>();
+        let mut enrichments = enrich(&logins, token)
+            .await
+            .context("enriching stargazers")?;
+
+        for star in &mut stars {
+            star.node.enrichment = enrichments.remove(&star.node.login);
+        }
+    }
+
+    let extension = match args.format {
+        FetchFormat::Csv => "csv",
+        FetchFormat::Atom => "atom",
+    };
+
+    let path = args.path.unwrap_or_else(|| {
+        format!(
+            "{}-{}-stargazers.{extension}",
+            args.repo.owner, args.repo.repository
+        )
+        .into()
+    });
+
+    eprintln!("writing {} records to {}.", stars.len(), path.display());
+
+    match args.format {
+        FetchFormat::Csv => {
+            let mut writer = csv::Writer::from_path(&path)
+                .with_context(|| format!("opening output file path at {}", path.display()))?;
+
+            for star in stars {
+                let enrichment = star.node.enrichment;
+                writer
+                    .serialize(Row {
+                        date: star.starred_at,
+                        username: star.node.login,
+                        email: star.node.email,
+                        location: star.node.location,
+                        followers: star.node.followers.total_count,
+                        following: star.node.following.total_count,
+                        hireable: star.node.is_hireable,
+                        bio: enrichment.as_ref().and_then(|e| e.bio.clone()),
+                        company: enrichment.as_ref().and_then(|e| e.company.clone()),
+                        twitter: enrichment.as_ref().and_then(|e| e.twitter_username.clone()),
+                        created_at: enrichment.as_ref().map(|e| e.created_at),
+                        public_repos: enrichment.as_ref().map(|e| e.public_repos),
+                        top_repository: enrichment
+                            .as_ref()
+                            .and_then(|e| e.top_repository.as_ref())
+                            .map(format_repo),
+                        pinned_repositories: enrichment.as_ref().map(|e| {
+                            e.pinned_repositories
+                                .iter()
+                                .map(format_repo)
+                                .collect::<Vec<_>>()
+                                .join("; ")
+                        }),
+                    })
+                    .context("writing star record")?;
+            }
+        }
+        FetchFormat::Atom => {
+            let feed = render_atom(&args.repo, stars)?;
+            std::fs::write(&path, feed)
+                .with_context(|| format!("writing Atom feed to {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Crawls a repository's stargazers and writes a cumulative time series.
+async fn run_history(args: HistoryArgs, token: &str) -> Result<()> {
+    let stars = fetch(&args.repo, token, args.refresh)
+        .await
+        .context("fetching stargazers")?;
+
+    let series = time_series(stars, args.granularity);
+
+    let extension = match args.format {
+        HistoryFormat::Csv => "csv",
+        HistoryFormat::Svg => "svg",
+    };
+
+    let path = args.path.unwrap_or_else(|| {
+        format!(
+            "{}-{}-history.{extension}",
+            args.repo.owner, args.repo.repository
+        )
+        .into()
+    });
+
+    eprintln!("writing {} points to {}.", series.len(), path.display());
+
+    match args.format {
+        HistoryFormat::Csv => {
+            let mut writer = csv::Writer::from_path(&path)
+                .with_context(|| format!("opening output file path at {}", path.display()))?;
+
+            for row in series {
+                writer.serialize(row).context("writing history point")?;
+            }
+        }
+        HistoryFormat::Svg => {
+            std::fs::write(&path, render_svg(&series))
+                .with_context(|| format!("writing SVG to {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The OAuth client id used for the device flow. This is the public client id
+/// of the registered application and is safe to ship in the binary.
+const CLIENT_ID: &str = "Iv1.b507a08c87ecfe98";
+
+/// The scopes requested during authorization.
+const SCOPES: &str = "read:user public_repo";
+
+/// The device-code response from the authorization endpoint.
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    /// The code used to poll for the access token.
+    device_code: String,
+
+    /// The code the user enters at the verification URL.
+    user_code: String,
+
+    /// The URL the user visits to enter the code.
+    verification_uri: String,
+
+    /// The minimum number of seconds to wait between polls.
+    interval: u64,
+}
+
+/// The access-token response from the token endpoint.
+#[derive(Debug, Deserialize)]
+struct AccessToken {
+    /// The access token, once authorization completes.
+    access_token: Option<String>,
+
+    /// The error code while authorization is still pending or has failed.
+    error: Option<String>,
+
+    /// An updated polling interval, sent with a `slow_down` error.
+    interval: Option<u64>,
+}
+
+/// Runs GitHub's OAuth device-authorization flow, prompting the user to enter
+/// a code at the verification URL and polling until an access token is issued.
+async fn device_flow() -> Result<String> {
+    let client = reqwest::Client::new();
+
+    let device: DeviceCode = client
+        .post("https://github.com/login/device/code")
+        .header(ACCEPT, "application/json")
+        .header(USER_AGENT, "star-tracker/v0")
+        .form(&[("client_id", CLIENT_ID), ("scope", SCOPES)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("requesting device code")?;
+
+    eprintln!(
+        "To authenticate, open {} and enter the code: {}",
+        device.verification_uri, device.user_code
+    );
+
+    let mut interval = device.interval;
+    loop {
+        sleep(Duration::from_secs(interval)).await;
+
+        let response: AccessToken = client
+            .post("https://github.com/login/oauth/access_token")
+            .header(ACCEPT, "application/json")
+            .header(USER_AGENT, "star-tracker/v0")
+            .form(&[
+                ("client_id", CLIENT_ID),
+                ("device_code", device.device_code.as_str()),
+                (
+                    "grant_type",
+                    "urn:ietf:params:oauth:grant-type:device_code",
+                ),
+            ])
+            .send()
+            .await?
+            .json()
+            .await
+            .context("polling for access token")?;
+
+        if let Some(token) = response.access_token {
+            return Ok(token);
+        }
+
+        match response.error.as_deref() {
+            Some("authorization_pending") => continue,
+            Some("slow_down") => interval = response.interval.unwrap_or(interval + 5),
+            Some(error) => bail!("device authorization failed: {error}"),
+            None => bail!("device authorization failed: no token and no error returned"),
+        }
+    }
+}
+
+/// The path of the cached token in the user's config directory.
+fn token_path() -> Result<PathBuf> {
+    let dir = dirs::config_dir().ok_or_else(|| eyre!("could not determine config directory"))?;
+    Ok(dir.join("track-stars").join("token"))
+}
+
+/// Loads the cached token, if one has been saved.
+fn load_cached_token() -> Result<Option<String>> {
+    let path = token_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let token = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading cached token at {}", path.display()))?;
+    Ok(Some(token.trim().to_string()))
+}
+
+/// Persists a token to the config directory so later runs can reuse it. The
+/// token is a credential, so the file is created with owner-only permissions.
+fn save_token(token: &str) -> Result<()> {
+    let path = token_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating config directory at {}", parent.display()))?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("opening token file at {}", path.display()))?;
+
+    // `mode` above only applies when the file is created; tighten an existing
+    // file too so a re-auth never leaves a world-readable credential behind.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("setting permissions on {}", path.display()))?;
+    }
+
+    std::io::Write::write_all(&mut file, token.as_bytes())
+        .with_context(|| format!("writing token to {}", path.display()))?;
+    Ok(())
+}
+
+/// Resolves a token to authenticate with, preferring `GH_TOKEN`, then a cached
+/// token, and finally falling back to the interactive device flow.
+async fn resolve_token() -> Result<String> {
+    if let Ok(token) = env::var("GH_TOKEN") {
+        return Ok(token);
+    }
+
+    if let Some(token) = load_cached_token()? {
+        return Ok(token);
+    }
+
+    eprintln!("No GH_TOKEN set; starting GitHub device authorization.");
+    let token = device_flow().await.context("authenticating with GitHub")?;
+    save_token(&token)?;
+    Ok(token)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install().context("installing color eyre")?;
 
     let args = Args::parse();
-    let token = env::var("GH_TOKEN").expect("github token to be present");
 
-    let stars = fetch(&args, &token).await.context("fetching stargazers")?;
+    match args.command {
+        Command::Auth => {
+            let token = device_flow().await.context("authenticating with GitHub")?;
+            save_token(&token)?;
+            eprintln!("Authentication successful; token cached.");
+            Ok(())
+        }
+        Command::Fetch(args) => {
+            let token = resolve_token().await?;
+            run_fetch(args, &token).await
+        }
+        Command::History(args) => {
+            let token = resolve_token().await?;
+            run_history(args, &token).await
+        }
+    }
+}
 
-    let path = args
-        .path
-        .unwrap_or_else(|| format!("{}-{}-stargazers.csv", args.owner, args.repository).into());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    eprintln!("writing {} records to {}.", stars.len(), path.display());
+    /// Builds a star with the given login and starred-at timestamp; the rest of
+    /// the user fields are irrelevant to the logic under test.
+    fn star(login: &str, starred_at: &str) -> Star {
+        Star {
+            starred_at: starred_at.parse().expect("a valid timestamp"),
+            node: User {
+                login: login.to_string(),
+                email: None,
+                location: None,
+                followers: FollowCount { total_count: 0 },
+                following: FollowCount { total_count: 0 },
+                is_hireable: false,
+                enrichment: None,
+            },
+        }
+    }
 
-    let mut writer = csv::Writer::from_path(&path)
-        .with_context(|| format!("opening output file path at {}", path.display()))?;
-
-    for star in stars {
-        writer
-            .serialize(Row {
-                date: star.starred_at,
-                username: star.node.login,
-                email: star.node.email,
-                location: star.node.location,
-                followers: star.node.followers.total_count,
-                following: star.node.following.total_count,
-                hireable: star.node.is_hireable,
-            })
-            .context("writing star record")?;
+    #[test]
+    fn deserializes_enrichment_response_with_not_found() {
+        // An aliased batch where one login resolves and one is unresolvable:
+        // GitHub returns partial `data` (the node is `null`) *and* a per-alias
+        // `NOT_FOUND` error for it — the shape that used to hard-fail the run.
+        let body = r#"{
+            "data": {
+                "u0": {
+                    "login": "octocat",
+                    "bio": "a cat",
+                    "company": "@github",
+                    "twitterUsername": "octocat",
+                    "createdAt": "2011-01-25T18:44:36Z",
+                    "repositories": { "totalCount": 8 },
+                    "topRepository": {
+                        "nodes": [
+                            { "name": "Hello-World", "stargazerCount": 2500, "forkCount": 1800 }
+                        ]
+                    },
+                    "pinnedItems": {
+                        "nodes": [
+                            { "name": "Spoon-Knife", "stargazerCount": 12000, "forkCount": 150000 }
+                        ]
+                    }
+                },
+                "u1": null
+            },
+            "errors": [
+                {
+                    "type": "NOT_FOUND",
+                    "path": ["u1"],
+                    "message": "Could not resolve to a User with the login of 'ghost'."
+                }
+            ]
+        }"#;
+
+        let response: EnrichResponse =
+            serde_json::from_str(body).expect("enrichment response to deserialize");
+        let users = response
+            .into_users()
+            .expect("NOT_FOUND errors to be tolerated");
+
+        let octocat = users
+            .get("u0")
+            .expect("u0 to be present")
+            .as_ref()
+            .expect("u0 to resolve to a user");
+        assert_eq!(octocat.login, "octocat");
+        assert_eq!(octocat.repositories.total_count, 8);
+        assert_eq!(octocat.top_repository.nodes[0].stargazer_count, 2500);
+        assert_eq!(octocat.pinned_items.nodes[0].fork_count, 150000);
+
+        // The unresolvable login is skipped, not fatal.
+        assert!(users.get("u1").expect("u1 to be present").is_none());
     }
 
-    Ok(())
+    #[test]
+    fn enrichment_bails_on_non_not_found_errors() {
+        // A genuine failure (e.g. a bad query) must still abort, even with data.
+        let body = r#"{
+            "data": { "u0": null },
+            "errors": [{ "type": "FORBIDDEN", "message": "insufficient scopes" }]
+        }"#;
+
+        let response: EnrichResponse =
+            serde_json::from_str(body).expect("enrichment response to deserialize");
+        assert!(response.into_users().is_err());
+    }
+
+    #[test]
+    fn time_series_emits_last_value_per_bucket() {
+        // Two stars in January, one in February; deliberately out of order to
+        // confirm the series is sorted ascending before accumulating.
+        let stars = vec![
+            star("b", "2024-01-20T00:00:00Z"),
+            star("a", "2024-01-10T00:00:00Z"),
+            star("c", "2024-02-05T00:00:00Z"),
+        ];
+
+        let series = time_series(stars, Granularity::Monthly);
+
+        let january: DateTime = "2024-01-01T00:00:00Z".parse().expect("a valid timestamp");
+        assert_eq!(series.len(), 2);
+        // The January bucket is floored to the first of the month and carries
+        // the *last* cumulative value in the bucket (2), not the first (1).
+        assert_eq!(series[0].timestamp, january);
+        assert_eq!(series[0].cumulative_count, 2);
+        assert_eq!(series[1].cumulative_count, 3);
+    }
+
+    #[test]
+    fn time_series_raw_keeps_every_point() {
+        let stars = vec![
+            star("a", "2024-01-10T00:00:00Z"),
+            star("b", "2024-01-20T00:00:00Z"),
+        ];
+
+        let series = time_series(stars, Granularity::Raw);
+
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].cumulative_count, 1);
+        assert_eq!(series[1].cumulative_count, 2);
+    }
+
+    #[test]
+    fn pace_does_not_throttle_while_healthy() {
+        let rate_limit = RateLimit {
+            cost: 1,
+            remaining: 5000,
+            reset_at: Utc::now() + chrono::Duration::minutes(30),
+        };
+
+        assert_eq!(pace(&rate_limit), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn pace_spreads_remaining_calls_until_reset() {
+        // 10 calls left over the next 100 seconds => ~10s between calls.
+        let rate_limit = RateLimit {
+            cost: 1,
+            remaining: 10,
+            reset_at: Utc::now() + chrono::Duration::seconds(100),
+        };
+
+        let delay = pace(&rate_limit).as_secs();
+        assert!((9..=10).contains(&delay), "unexpected delay: {delay}");
+    }
+
+    #[test]
+    fn pace_handles_exhausted_budget() {
+        // `remaining` of zero must not divide by zero.
+        let rate_limit = RateLimit {
+            cost: 1,
+            remaining: 0,
+            reset_at: Utc::now() + chrono::Duration::seconds(60),
+        };
+
+        assert!(pace(&rate_limit).as_secs() >= 59);
+    }
 }